@@ -312,6 +312,14 @@ namespace OpenAI.Harmony
     {
         [JsonPropertyName("auto_drop_analysis")]
         public bool AutoDropAnalysis { get; set; } = true;
+        [JsonPropertyName("system_author")]
+        public string? SystemAuthor { get; set; }
+        [JsonPropertyName("channel_overrides")]
+        public Dictionary<uint, string> ChannelOverrides { get; set; } = new();
+        [JsonPropertyName("max_messages")]
+        public int? MaxMessages { get; set; }
+        [JsonPropertyName("skip_empty_messages")]
+        public bool? SkipEmptyMessages { get; set; }
     }
     public class RenderOptions
     {
@@ -423,6 +423,13 @@ class Conversation(BaseModel):
 
 class RenderConversationConfig(BaseModel):
     auto_drop_analysis: bool = True
+    system_author: Optional[str] = None
+    channel_overrides: Dict[int, str] = Field(default_factory=dict)
+    max_messages: Optional[int] = None
+    skip_empty_messages: Optional[bool] = None
+
+    def to_dict(self) -> dict:
+        return self.model_dump(exclude_none=True)
 
 
 class RenderOptions(BaseModel):
@@ -462,10 +469,7 @@ class HarmonyEncoding:
             next_turn_role: Role for the next turn
             config: Optional RenderConversationConfig (default auto_drop_analysis=True)
         """
-        if config is None:
-            config_dict = {"auto_drop_analysis": True}
-        else:
-            config_dict = {"auto_drop_analysis": config.auto_drop_analysis}
+        config_dict = (config or RenderConversationConfig()).to_dict()
         return self._inner.render_conversation_for_completion(
             conversation_json=conversation.to_json(),
             next_turn_role=str(next_turn_role.value),
@@ -478,10 +482,7 @@ class HarmonyEncoding:
         config: Optional[RenderConversationConfig] = None,
     ) -> List[int]:
         """Render a conversation without appending a new role."""
-        if config is None:
-            config_dict = {"auto_drop_analysis": True}
-        else:
-            config_dict = {"auto_drop_analysis": config.auto_drop_analysis}
+        config_dict = (config or RenderConversationConfig()).to_dict()
         return self._inner.render_conversation(
             conversation_json=conversation.to_json(),
             config=config_dict,
@@ -493,10 +494,7 @@ class HarmonyEncoding:
         config: Optional[RenderConversationConfig] = None,
     ) -> List[int]:
         """Render a conversation for training."""
-        if config is None:
-            config_dict = {"auto_drop_analysis": True}
-        else:
-            config_dict = {"auto_drop_analysis": config.auto_drop_analysis}
+        config_dict = (config or RenderConversationConfig()).to_dict()
         return self._inner.render_conversation_for_training(
             conversation_json=conversation.to_json(),
             config=config_dict,
@@ -3,7 +3,7 @@ use serde::{
     de::{self, Visitor},
     Deserialize, Deserializer, Serialize,
 };
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet};
 use std::{fmt::Display, marker::PhantomData};
 
 #[serde_with::skip_serializing_none]
@@ -446,6 +446,31 @@ impl Conversation {
             messages: messages.into_iter().collect(),
         }
     }
+
+    /// A small canned conversation (system, user and a final assistant answer)
+    /// that renders cleanly with the standard encoding. Useful as a golden input.
+    pub fn example() -> Self {
+        Self::from_messages([
+            Message::from_role_and_content(Role::System, SystemContent::new()),
+            Message::from_role_and_content(Role::User, "What is 2 + 2?"),
+            Message::from_role_and_content(Role::Assistant, "2 + 2 equals 4.")
+                .with_channel("final"),
+        ])
+    }
+
+    /// Names of all tools addressed by assistant tool calls in this conversation.
+    ///
+    /// A tool call is an assistant message with a recipient other than `all`,
+    /// which is exactly what the renderer terminates with `<|call|>`.
+    pub fn referenced_tools(&self) -> BTreeSet<String> {
+        self.messages
+            .iter()
+            .filter(|msg| msg.author.role == Role::Assistant)
+            .filter_map(|msg| msg.recipient.as_deref())
+            .filter(|recipient| *recipient != "all")
+            .map(str::to_string)
+            .collect()
+    }
 }
 
 impl<'a> IntoIterator for &'a Conversation {
@@ -123,8 +123,11 @@ pub extern "C" fn harmony_encoding_name(handle: *mut c_void) -> *mut c_char {
 // All rendering functions will accept JSON strings and optional config JSON (or NULL).
 // They return a JSON string that encodes the token array (e.g. "[1,2,3]") or NULL on error.
 
-fn parse_render_config(config_json: Option<String>) -> Option<RenderConversationConfig> {
-    config_json.and_then(|s| serde_json::from_str::<RenderConversationConfig>(&s).ok())
+fn parse_render_config(config_json: Option<String>) -> Result<Option<RenderConversationConfig>, String> {
+    config_json
+        .map(|s| serde_json::from_str::<RenderConversationConfig>(&s))
+        .transpose()
+        .map_err(|e| format!("invalid config JSON: {}", e))
 }
 
 #[no_mangle]
@@ -161,7 +164,13 @@ pub extern "C" fn harmony_render_conversation_for_completion(
         }
     };
     let config_opt = unsafe { opt_cstr_to_opt_string(config_json) };
-    let rust_config = parse_render_config(config_opt);
+    let rust_config = match parse_render_config(config_opt) {
+        Ok(c) => c,
+        Err(e) => {
+            set_last_error(e);
+            return ptr::null_mut();
+        }
+    };
 
     match enc.render_conversation_for_completion(&conv, role, rust_config.as_ref()) {
         Ok(tokens) => {
@@ -206,7 +215,13 @@ pub extern "C" fn harmony_render_conversation(
         }
     };
     let config_opt = unsafe { opt_cstr_to_opt_string(config_json) };
-    let rust_config = parse_render_config(config_opt);
+    let rust_config = match parse_render_config(config_opt) {
+        Ok(c) => c,
+        Err(e) => {
+            set_last_error(e);
+            return ptr::null_mut();
+        }
+    };
 
     match enc.render_conversation(&conv, rust_config.as_ref()) {
         Ok(tokens) => serde_json::to_string(&tokens).map(|s| string_to_c(s)).unwrap_or_else(|e| {
@@ -245,7 +260,13 @@ pub extern "C" fn harmony_render_conversation_for_training(
         }
     };
     let config_opt = unsafe { opt_cstr_to_opt_string(config_json) };
-    let rust_config = parse_render_config(config_opt);
+    let rust_config = match parse_render_config(config_opt) {
+        Ok(c) => c,
+        Err(e) => {
+            set_last_error(e);
+            return ptr::null_mut();
+        }
+    };
 
     match enc.render_conversation_for_training(&conv, rust_config.as_ref()) {
         Ok(tokens) => serde_json::to_string(&tokens).map(|s| string_to_c(s)).unwrap_or_else(|e| {
@@ -259,6 +280,95 @@ pub extern "C" fn harmony_render_conversation_for_training(
     }
 }
 
+/// Returns the developer-section token delta of adding `new_tool_json` (a tool description)
+/// to `existing_tools_json` (a JSON array of tool descriptions), or -1 on error.
+#[no_mangle]
+pub extern "C" fn harmony_tool_token_cost(
+    handle: *mut c_void,
+    existing_tools_json: *const c_char,
+    new_tool_json: *const c_char,
+    config_json: *const c_char, // optional JSON string or NULL
+) -> i64 {
+    if handle.is_null() {
+        set_last_error("null handle");
+        return -1;
+    }
+    let enc = unsafe { &*(handle as *mut HarmonyEncoding) };
+
+    let existing_str = unsafe { opt_cstr_to_opt_string(existing_tools_json) };
+    let new_tool_str = unsafe { opt_cstr_to_opt_string(new_tool_json) };
+    if existing_str.is_none() || new_tool_str.is_none() {
+        set_last_error("existing_tools_json or new_tool_json is null/invalid");
+        return -1;
+    }
+    let existing: Vec<crate::chat::ToolDescription> = match serde_json::from_str(&existing_str.unwrap()) {
+        Ok(v) => v,
+        Err(e) => {
+            set_last_error(format!("invalid tools JSON: {}", e));
+            return -1;
+        }
+    };
+    let new_tool: crate::chat::ToolDescription = match serde_json::from_str(&new_tool_str.unwrap()) {
+        Ok(v) => v,
+        Err(e) => {
+            set_last_error(format!("invalid tool JSON: {}", e));
+            return -1;
+        }
+    };
+    let config_opt = unsafe { opt_cstr_to_opt_string(config_json) };
+    let rust_config = match parse_render_config(config_opt) {
+        Ok(c) => c,
+        Err(e) => {
+            set_last_error(e);
+            return -1;
+        }
+    };
+
+    match enc.tool_token_cost(&existing, &new_tool, rust_config.as_ref()) {
+        Ok(delta) => delta,
+        Err(e) => {
+            set_last_error(e.to_string());
+            -1
+        }
+    }
+}
+
+/// Render a conversation as a readable transcript; tool calls and results are
+/// indented by `indent` spaces under the assistant turn that initiated them.
+#[no_mangle]
+pub extern "C" fn harmony_render_transcript(
+    handle: *mut c_void,
+    conversation_json: *const c_char,
+    indent: u32,
+) -> *mut c_char {
+    if handle.is_null() {
+        set_last_error("null handle");
+        return ptr::null_mut();
+    }
+    let enc = unsafe { &*(handle as *mut HarmonyEncoding) };
+
+    let conversation_str = unsafe { opt_cstr_to_opt_string(conversation_json) };
+    if conversation_str.is_none() {
+        set_last_error("conversation_json is null/invalid");
+        return ptr::null_mut();
+    }
+    let conv: crate::chat::Conversation = match serde_json::from_str(&conversation_str.unwrap()) {
+        Ok(c) => c,
+        Err(e) => {
+            set_last_error(format!("invalid conversation JSON: {}", e));
+            return ptr::null_mut();
+        }
+    };
+
+    match enc.render_transcript(&conv, indent as usize) {
+        Ok(s) => string_to_c(s),
+        Err(e) => {
+            set_last_error(e.to_string());
+            ptr::null_mut()
+        }
+    }
+}
+
 #[no_mangle]
 pub extern "C" fn harmony_render(
     handle: *mut c_void,
@@ -348,6 +458,162 @@ pub extern "C" fn harmony_parse_messages_from_completion_tokens(
     }
 }
 
+/// Parse full completion tokens (each message starting with `<|start|>`) and return
+/// the tool calls found as JSON, each with its `arguments_token_range`.
+#[no_mangle]
+pub extern "C" fn harmony_parse_tool_calls_with_spans(
+    handle: *mut c_void,
+    tokens_json: *const c_char, // expect JSON array e.g. "[1,2,3]"
+) -> *mut c_char {
+    if handle.is_null() {
+        set_last_error("null handle");
+        return ptr::null_mut();
+    }
+    let enc = unsafe { &*(handle as *mut HarmonyEncoding) };
+
+    let tokens_str = unsafe { opt_cstr_to_opt_string(tokens_json) };
+    if tokens_str.is_none() {
+        set_last_error("tokens_json is null/invalid");
+        return ptr::null_mut();
+    }
+    let tokens: Vec<u32> = match serde_json::from_str(&tokens_str.unwrap()) {
+        Ok(v) => v,
+        Err(e) => {
+            set_last_error(format!("invalid tokens JSON: {}", e));
+            return ptr::null_mut();
+        }
+    };
+
+    match enc.parse_tool_calls_with_spans(tokens, None) {
+        Ok(spans) => serde_json::to_string(&spans).map(string_to_c).unwrap_or_else(|e| {
+            set_last_error(format!("serialisation error: {}", e));
+            ptr::null_mut()
+        }),
+        Err(e) => {
+            set_last_error(e.to_string());
+            ptr::null_mut()
+        }
+    }
+}
+
+/// Apply best-effort repairs to a malformed token stream. Returns JSON
+/// `{"tokens": [...], "repairs": [...]}`; a clean stream comes back unchanged.
+#[no_mangle]
+pub extern "C" fn harmony_repair_stream(
+    handle: *mut c_void,
+    tokens_json: *const c_char, // expect JSON array e.g. "[1,2,3]"
+) -> *mut c_char {
+    if handle.is_null() {
+        set_last_error("null handle");
+        return ptr::null_mut();
+    }
+    let enc = unsafe { &*(handle as *mut HarmonyEncoding) };
+
+    let tokens_str = unsafe { opt_cstr_to_opt_string(tokens_json) };
+    if tokens_str.is_none() {
+        set_last_error("tokens_json is null/invalid");
+        return ptr::null_mut();
+    }
+    let tokens: Vec<u32> = match serde_json::from_str(&tokens_str.unwrap()) {
+        Ok(v) => v,
+        Err(e) => {
+            set_last_error(format!("invalid tokens JSON: {}", e));
+            return ptr::null_mut();
+        }
+    };
+
+    match enc.repair_stream(tokens) {
+        Ok(repaired) => serde_json::to_string(&repaired).map(string_to_c).unwrap_or_else(|e| {
+            set_last_error(format!("serialisation error: {}", e));
+            ptr::null_mut()
+        }),
+        Err(e) => {
+            set_last_error(e.to_string());
+            ptr::null_mut()
+        }
+    }
+}
+
+/// Returns the concatenated content of all `commentary` channel messages, or NULL
+/// if there are none (without setting the last error).
+#[no_mangle]
+pub extern "C" fn harmony_extract_commentary(
+    handle: *mut c_void,
+    tokens_json: *const c_char, // expect JSON array e.g. "[1,2,3]"
+    role: *const c_char,        // optional
+) -> *mut c_char {
+    if handle.is_null() {
+        set_last_error("null handle");
+        return ptr::null_mut();
+    }
+    let enc = unsafe { &*(handle as *mut HarmonyEncoding) };
+
+    let tokens_str = unsafe { opt_cstr_to_opt_string(tokens_json) };
+    if tokens_str.is_none() {
+        set_last_error("tokens_json is null/invalid");
+        return ptr::null_mut();
+    }
+    let tokens: Vec<u32> = match serde_json::from_str(&tokens_str.unwrap()) {
+        Ok(v) => v,
+        Err(e) => {
+            set_last_error(format!("invalid tokens JSON: {}", e));
+            return ptr::null_mut();
+        }
+    };
+
+    let role_parsed = unsafe { opt_cstr_to_opt_string(role) }
+        .map(|r| Role::try_from(r.as_str()))
+        .transpose()
+        .map_err(|_| ())
+        .ok()
+        .flatten();
+
+    match enc.extract_channel_content(tokens, role_parsed, "commentary") {
+        Ok(Some(s)) => string_to_c(s),
+        Ok(None) => ptr::null_mut(),
+        Err(e) => {
+            set_last_error(e.to_string());
+            ptr::null_mut()
+        }
+    }
+}
+
+/// Returns 1 if the token stream ends at a message/header boundary, 0 if it does not
+/// and -1 on error.
+#[no_mangle]
+pub extern "C" fn harmony_ends_at_message_boundary(
+    handle: *mut c_void,
+    tokens_json: *const c_char, // expect JSON array e.g. "[1,2,3]"
+) -> i32 {
+    if handle.is_null() {
+        set_last_error("null handle");
+        return -1;
+    }
+    let enc = unsafe { &*(handle as *mut HarmonyEncoding) };
+
+    let tokens_str = unsafe { opt_cstr_to_opt_string(tokens_json) };
+    if tokens_str.is_none() {
+        set_last_error("tokens_json is null/invalid");
+        return -1;
+    }
+    let tokens: Vec<u32> = match serde_json::from_str(&tokens_str.unwrap()) {
+        Ok(v) => v,
+        Err(e) => {
+            set_last_error(format!("invalid tokens JSON: {}", e));
+            return -1;
+        }
+    };
+
+    match enc.ends_at_message_boundary(tokens) {
+        Ok(true) => 1,
+        Ok(false) => 0,
+        Err(e) => {
+            set_last_error(e.to_string());
+            -1
+        }
+    }
+}
+
 #[no_mangle]
 pub extern "C" fn harmony_decode_utf8(
     handle: *mut c_void,
@@ -446,6 +712,64 @@ pub extern "C" fn harmony_encode(
     })
 }
 
+/// Encodes `text` and sets `*out_over` to 1 when the token count exceeds `max_tokens`.
+/// `*out_tokens` (may be NULL) receives the tokens as a JSON array. Returns 0 on success, -1 on error.
+#[no_mangle]
+pub extern "C" fn harmony_encode_check_limit(
+    handle: *mut c_void,
+    text: *const c_char,
+    allowed_special_json: *const c_char, // optional JSON array of strings
+    max_tokens: u32,
+    out_tokens: *mut *mut c_char,
+    out_over: *mut i32,
+) -> i32 {
+    if handle.is_null() {
+        set_last_error("null handle");
+        return -1;
+    }
+    if out_over.is_null() {
+        set_last_error("out_over is null");
+        return -1;
+    }
+    let enc = unsafe { &*(handle as *mut HarmonyEncoding) };
+
+    let text_str = unsafe { opt_cstr_to_opt_string(text) }.unwrap_or_default();
+    let allowed_opt = unsafe { opt_cstr_to_opt_string(allowed_special_json) };
+    let allowed_set = allowed_opt
+        .and_then(|s| serde_json::from_str::<Vec<String>>(&s).ok())
+        .unwrap_or_default();
+    let allowed_refset: std::collections::HashSet<&str> =
+        allowed_set.iter().map(|s| s.as_str()).collect();
+
+    let (tokens, over) = enc.encode_check_limit(&text_str, &allowed_refset, max_tokens as usize);
+    if !out_tokens.is_null() {
+        match serde_json::to_string(&tokens) {
+            Ok(s) => unsafe { *out_tokens = string_to_c(s) },
+            Err(e) => {
+                set_last_error(format!("serialisation error: {}", e));
+                return -1;
+            }
+        }
+    }
+    unsafe { *out_over = if over { 1 } else { 0 } };
+    0
+}
+
+#[no_mangle]
+pub extern "C" fn harmony_word_token_alignment(handle: *mut c_void, text: *const c_char) -> *mut c_char {
+    if handle.is_null() {
+        set_last_error("null handle");
+        return ptr::null_mut();
+    }
+    let enc = unsafe { &*(handle as *mut HarmonyEncoding) };
+    let text_str = unsafe { opt_cstr_to_opt_string(text) }.unwrap_or_default();
+
+    serde_json::to_string(&enc.word_token_alignment(&text_str)).map(string_to_c).unwrap_or_else(|e| {
+        set_last_error(format!("serialisation error: {}", e));
+        ptr::null_mut()
+    })
+}
+
 #[no_mangle]
 pub extern "C" fn harmony_special_tokens(handle: *mut c_void) -> *mut c_char {
     if handle.is_null() {
@@ -514,6 +838,34 @@ pub extern "C" fn harmony_stop_tokens_for_assistant_actions(handle: *mut c_void)
     }
 }
 
+#[no_mangle]
+pub extern "C" fn harmony_terminator_tokens(handle: *mut c_void, terminator: *const c_char) -> *mut c_char {
+    if handle.is_null() {
+        set_last_error("null handle");
+        return ptr::null_mut();
+    }
+    let enc = unsafe { &*(handle as *mut HarmonyEncoding) };
+
+    let terminator_str = match unsafe { opt_cstr_to_opt_string(terminator) } {
+        Some(s) => s,
+        None => {
+            set_last_error("terminator is null/invalid");
+            return ptr::null_mut();
+        }
+    };
+
+    match enc.terminator_tokens(&terminator_str) {
+        Ok(tokens) => serde_json::to_string(&tokens).map(string_to_c).unwrap_or_else(|e| {
+            set_last_error(format!("serialisation error: {}", e));
+            ptr::null_mut()
+        }),
+        Err(e) => {
+            set_last_error(e.to_string());
+            ptr::null_mut()
+        }
+    }
+}
+
 // -------------------- StreamableParser handle --------------------
 #[no_mangle]
 pub extern "C" fn harmony_streamable_parser_new(
@@ -586,6 +938,20 @@ pub extern "C" fn harmony_streamable_parser_current_content(handle: *mut c_void)
     }
 }
 
+#[no_mangle]
+pub extern "C" fn harmony_streamable_parser_current_message(handle: *mut c_void) -> *mut c_char {
+    if handle.is_null() {
+        set_last_error("null handle");
+        return ptr::null_mut();
+    }
+    let parser = unsafe { &*(handle as *mut StreamableParser) };
+    match parser.current_message_partial() {
+        Ok(serde_json::Value::Null) => ptr::null_mut(),
+        Ok(v) => string_to_c(v.to_string()),
+        Err(e) => { set_last_error(e.to_string()); ptr::null_mut() }
+    }
+}
+
 #[no_mangle]
 pub extern "C" fn harmony_streamable_parser_current_role(handle: *mut c_void) -> *mut c_char {
     if handle.is_null() {
@@ -684,6 +1050,113 @@ pub extern "C" fn harmony_streamable_parser_current_channel(handle: *mut c_void)
     parser.current_channel().map(|s| CString::new(s).unwrap().into_raw()).unwrap_or(ptr::null_mut())
 }
 
+#[no_mangle]
+pub extern "C" fn harmony_parser_transition_table() -> *mut c_char {
+    match serde_json::to_string(&StreamableParser::transition_table()) {
+        Ok(s) => string_to_c(s),
+        Err(e) => { set_last_error(e.to_string()); ptr::null_mut() }
+    }
+}
+
+// Loads the two encodings named by `name_a` and `name_b`, setting the last error on failure.
+fn load_encoding_pair(name_a: *const c_char, name_b: *const c_char) -> Option<Vec<HarmonyEncoding>> {
+    let names = unsafe { (opt_cstr_to_opt_string(name_a), opt_cstr_to_opt_string(name_b)) };
+    let (name_a, name_b) = match names {
+        (Some(a), Some(b)) => (a, b),
+        _ => {
+            set_last_error("name_a or name_b is null/invalid");
+            return None;
+        }
+    };
+    let mut encodings = Vec::with_capacity(2);
+    for name in [name_a, name_b] {
+        let parsed: HarmonyEncodingName = match name.parse() {
+            Ok(v) => v,
+            Err(e) => {
+                set_last_error(format!("invalid encoding name: {}", e));
+                return None;
+            }
+        };
+        match load_harmony_encoding(parsed) {
+            Ok(enc) => encodings.push(enc),
+            Err(e) => {
+                set_last_error(e.to_string());
+                return None;
+            }
+        }
+    }
+    Some(encodings)
+}
+
+// -------------------- Utility: render_diff_across_encodings --------------------
+#[no_mangle]
+pub extern "C" fn harmony_render_diff_across_encodings(
+    name_a: *const c_char,
+    name_b: *const c_char,
+    conversation_json: *const c_char,
+    next_turn_role: *const c_char, // optional, renders for completion when set
+    config_json: *const c_char,    // optional JSON string or NULL
+) -> *mut c_char {
+    let encodings = match load_encoding_pair(name_a, name_b) {
+        Some(v) => v,
+        None => return ptr::null_mut(),
+    };
+
+    let conversation_str = unsafe { opt_cstr_to_opt_string(conversation_json) };
+    if conversation_str.is_none() {
+        set_last_error("conversation_json is null/invalid");
+        return ptr::null_mut();
+    }
+    let conv: crate::chat::Conversation = match serde_json::from_str(&conversation_str.unwrap()) {
+        Ok(c) => c,
+        Err(e) => {
+            set_last_error(format!("invalid conversation JSON: {}", e));
+            return ptr::null_mut();
+        }
+    };
+    let role = match unsafe { opt_cstr_to_opt_string(next_turn_role) } {
+        Some(r) => match Role::try_from(r.as_str()) {
+            Ok(r) => Some(r),
+            Err(_) => {
+                set_last_error("unknown role");
+                return ptr::null_mut();
+            }
+        },
+        None => None,
+    };
+    let config_opt = unsafe { opt_cstr_to_opt_string(config_json) };
+    let rust_config = match parse_render_config(config_opt) {
+        Ok(c) => c,
+        Err(e) => {
+            set_last_error(e);
+            return ptr::null_mut();
+        }
+    };
+
+    match encodings[0].render_diff(&encodings[1], &conv, role, rust_config.as_ref()) {
+        Ok(s) => string_to_c(s),
+        Err(e) => {
+            set_last_error(e.to_string());
+            ptr::null_mut()
+        }
+    }
+}
+
+// -------------------- Utility: special_tokens_diff --------------------
+#[no_mangle]
+pub extern "C" fn harmony_special_tokens_diff(name_a: *const c_char, name_b: *const c_char) -> *mut c_char {
+    let encodings = match load_encoding_pair(name_a, name_b) {
+        Some(v) => v,
+        None => return ptr::null_mut(),
+    };
+
+    let diff = encodings[0].special_tokens_diff(&encodings[1]);
+    serde_json::to_string(&diff).map(string_to_c).unwrap_or_else(|e| {
+        set_last_error(format!("serialisation error: {}", e));
+        ptr::null_mut()
+    })
+}
+
 // -------------------- Utility: get_tool_namespace_config --------------------
 #[no_mangle]
 pub extern "C" fn harmony_get_tool_namespace_config(tool: *const c_char) -> *mut c_char {
@@ -710,3 +1183,41 @@ pub extern "C" fn harmony_get_tool_namespace_config(tool: *const c_char) -> *mut
         Err(e) => { set_last_error(e.to_string()); ptr::null_mut() }
     }
 }
+
+// -------------------- Utility: example_conversation --------------------
+#[no_mangle]
+pub extern "C" fn harmony_example_conversation() -> *mut c_char {
+    match serde_json::to_string(&crate::chat::Conversation::example()) {
+        Ok(s) => string_to_c(s),
+        Err(e) => {
+            set_last_error(format!("serialisation error: {}", e));
+            ptr::null_mut()
+        }
+    }
+}
+
+// -------------------- Utility: conversation_referenced_tools --------------------
+#[no_mangle]
+pub extern "C" fn harmony_conversation_referenced_tools(conversation_json: *const c_char) -> *mut c_char {
+    let conversation_str = unsafe { opt_cstr_to_opt_string(conversation_json) };
+    let conversation_str = match conversation_str {
+        Some(s) => s,
+        None => {
+            set_last_error("conversation_json is null/invalid");
+            return ptr::null_mut();
+        }
+    };
+    let conv: crate::chat::Conversation = match serde_json::from_str(&conversation_str) {
+        Ok(c) => c,
+        Err(e) => {
+            set_last_error(format!("invalid conversation JSON: {}", e));
+            return ptr::null_mut();
+        }
+    };
+
+    let tools: Vec<String> = conv.referenced_tools().into_iter().collect();
+    serde_json::to_string(&tools).map(string_to_c).unwrap_or_else(|e| {
+        set_last_error(format!("serialisation error: {}", e));
+        ptr::null_mut()
+    })
+}
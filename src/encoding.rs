@@ -1,10 +1,14 @@
 use crate::{
-    chat::{Author, Content, Message, ReasoningEffort, Role, SystemContent, TextContent},
+    chat::{
+        Author, Content, DeveloperContent, Message, ReasoningEffort, Role, SystemContent,
+        TextContent, ToolDescription,
+    },
     tiktoken::{CoreBPE, Rank},
 };
 use anyhow::Context as _;
 use std::{
-    collections::{HashMap, HashSet},
+    borrow::Cow,
+    collections::{BTreeSet, HashMap, HashSet},
     sync::Arc,
     vec,
 };
@@ -184,6 +188,18 @@ impl HarmonyEncoding {
             })
             .collect()
     }
+
+    /// Tokens emitted for the named message terminator: `end` (`<|end|>`),
+    /// `return` (`<|return|>`) or `call` (`<|call|>`).
+    pub fn terminator_tokens(&self, terminator: &str) -> anyhow::Result<Vec<Rank>> {
+        let t = match terminator {
+            "end" => FormattingToken::EndMessage,
+            "return" => FormattingToken::EndMessageDoneSampling,
+            "call" => FormattingToken::EndMessageAssistantToTool,
+            _ => anyhow::bail!("unknown terminator: {}", terminator),
+        };
+        Ok(vec![self.render_formatting_token(t)?])
+    }
 }
 
 // Methods for rendering conversations
@@ -200,6 +216,15 @@ impl HarmonyEncoding {
         B: Extend<Rank>,
     {
         let messages: Vec<_> = conversation.into_iter().collect();
+        if let Some(config) = config {
+            Self::validate_render_config(&messages, config)?;
+        }
+        let messages: Vec<Cow<'a, Message>> = messages
+            .into_iter()
+            .enumerate()
+            .map(|(idx, msg)| Self::apply_render_config(idx, msg, config))
+            .filter(|msg| !Self::is_skipped_empty_message(msg, config))
+            .collect();
         let has_function_tools = messages.iter().any(|msg| {
             msg.content.iter().any(|c| {
                 if let Content::DeveloperContent(dev) = c {
@@ -304,8 +329,17 @@ impl HarmonyEncoding {
         let messages: Vec<&Message> = conversation.into_iter().collect();
         let mut out = vec![];
         self.render_conversation_into(messages.iter().copied(), &mut out, config)?;
-        if let Some(last) = messages.last() {
-            if last.author.role == Role::Assistant && last.channel.as_deref() == Some("final") {
+        // the last message actually rendered, which `skip_empty_messages` may have moved
+        let last_rendered = messages
+            .iter()
+            .enumerate()
+            .rev()
+            .find(|(_, msg)| !Self::is_skipped_empty_message(msg, config));
+        if let Some((last_idx, last)) = last_rendered {
+            let last_channel = config
+                .and_then(|c| c.channel_overrides.get(&last_idx))
+                .or(last.channel.as_ref());
+            if last.author.role == Role::Assistant && last_channel.is_some_and(|c| c == "final") {
                 if let Some(last_token) = out.last_mut() {
                     *last_token =
                         self.render_formatting_token(FormattingToken::EndMessageDoneSampling)?;
@@ -329,6 +363,85 @@ impl HarmonyEncoding {
         Ok(out)
     }
 
+    /// Render a conversation as a human-readable transcript, one `[header] content`
+    /// entry per message.
+    ///
+    /// Tool calls and tool results are indented by `indent` spaces so that they
+    /// nest under the assistant turn that initiated them.
+    pub fn render_transcript<'a, I>(&self, conversation: I, indent: usize) -> anyhow::Result<String>
+    where
+        I: IntoIterator<Item = &'a Message>,
+    {
+        let mut entries = Vec::new();
+        for message in conversation {
+            let mut header = match (&message.author.role, &message.author.name) {
+                (Role::Tool, Some(name)) => name.clone(),
+                (role, Some(name)) => format!("{role}:{name}"),
+                (role, None) => role.to_string(),
+            };
+            if let Some(channel) = &message.channel {
+                header.push_str(&format!("/{channel}"));
+            }
+            let recipient = message.recipient.as_deref().filter(|r| *r != "all");
+            if let Some(recipient) = recipient {
+                header.push_str(&format!(" -> {recipient}"));
+            }
+
+            let mut content_tokens = Vec::new();
+            for content in &message.content {
+                Render::<Content>::render(self, content, &mut content_tokens, None)?;
+            }
+            let text = self.tokenizer.decode_utf8(&content_tokens)?;
+
+            let is_tool_interaction = message.author.role == Role::Tool
+                || (message.author.role == Role::Assistant && recipient.is_some());
+            let prefix = if is_tool_interaction {
+                " ".repeat(indent)
+            } else {
+                String::new()
+            };
+            let mut lines = text.split('\n');
+            let mut entry = format!("{prefix}[{header}] {}", lines.next().unwrap_or_default());
+            for line in lines {
+                entry.push('\n');
+                entry.push_str(&prefix);
+                entry.push_str(line);
+            }
+            entries.push(entry);
+        }
+        Ok(entries.join("\n"))
+    }
+
+    /// Marginal number of developer-section tokens spent by exposing `new_tool`
+    /// as a function tool in addition to `existing_tools`.
+    pub fn tool_token_cost(
+        &self,
+        existing_tools: &[ToolDescription],
+        new_tool: &ToolDescription,
+        config: Option<&RenderConversationConfig>,
+    ) -> anyhow::Result<i64> {
+        // Only `auto_drop_analysis` carries over: the per-message settings (overrides,
+        // limits) describe the caller's conversation, not this single developer message.
+        let config = config.map(|c| RenderConversationConfig {
+            auto_drop_analysis: c.auto_drop_analysis,
+            ..Default::default()
+        });
+        let developer_tokens = |tools: Vec<ToolDescription>| -> anyhow::Result<usize> {
+            let dev = if tools.is_empty() {
+                DeveloperContent::new()
+            } else {
+                DeveloperContent::new().with_function_tools(tools)
+            };
+            let message = Message::from_role_and_content(Role::Developer, dev);
+            Ok(self.render_conversation([&message], config.as_ref())?.len())
+        };
+        let without = developer_tokens(existing_tools.to_vec())?;
+        let mut tools = existing_tools.to_vec();
+        tools.push(new_tool.clone());
+        let with = developer_tokens(tools)?;
+        Ok(with as i64 - without as i64)
+    }
+
     /// Render a single message into tokens.
     pub fn render(
         &self,
@@ -354,8 +467,256 @@ impl HarmonyEncoding {
     }
 }
 
+// Methods for comparing encodings
+impl HarmonyEncoding {
+    /// Render `conversation` with both `self` and `other` and summarise how the
+    /// decoded renders differ, as a line diff of `-` (self) and `+` (other) lines.
+    ///
+    /// When `next_turn_role` is set the conversation is rendered for completion.
+    /// Returns an empty string when both renders produce identical tokens.
+    pub fn render_diff<'a, I>(
+        &self,
+        other: &HarmonyEncoding,
+        conversation: I,
+        next_turn_role: Option<Role>,
+        config: Option<&RenderConversationConfig>,
+    ) -> anyhow::Result<String>
+    where
+        I: IntoIterator<Item = &'a Message>,
+    {
+        let messages: Vec<&Message> = conversation.into_iter().collect();
+        let render = |enc: &HarmonyEncoding| -> anyhow::Result<(Vec<Rank>, String)> {
+            let tokens = match &next_turn_role {
+                Some(role) => enc.render_conversation_for_completion(
+                    messages.iter().copied(),
+                    role.clone(),
+                    config,
+                )?,
+                None => enc.render_conversation(messages.iter().copied(), config)?,
+            };
+            let text = enc.tokenizer.decode_utf8(&tokens)?;
+            Ok((tokens, text))
+        };
+        let (tokens_a, text_a) = render(self)?;
+        let (tokens_b, text_b) = render(other)?;
+        if tokens_a == tokens_b {
+            return Ok(String::new());
+        }
+
+        let mut out = vec![
+            format!("--- {} ({} tokens)", self.name, tokens_a.len()),
+            format!("+++ {} ({} tokens)", other.name, tokens_b.len()),
+        ];
+        if text_a == text_b {
+            out.push("decoded text is identical, token ids differ".to_string());
+        } else {
+            out.extend(line_diff(&text_a, &text_b));
+        }
+        Ok(out.join("\n"))
+    }
+
+    /// Compare the special token names of `self` and `other`: `added` are only in
+    /// `other`, `removed` are only in `self`.
+    pub fn special_tokens_diff(&self, other: &HarmonyEncoding) -> SpecialTokensDiff {
+        let ours: BTreeSet<&str> = self.tokenizer.special_tokens().into_iter().collect();
+        let theirs: BTreeSet<&str> = other.tokenizer.special_tokens().into_iter().collect();
+        SpecialTokensDiff {
+            added: theirs.difference(&ours).map(|s| s.to_string()).collect(),
+            removed: ours.difference(&theirs).map(|s| s.to_string()).collect(),
+            common: ours.intersection(&theirs).map(|s| s.to_string()).collect(),
+        }
+    }
+
+    /// Encode `text` and report whether the result is longer than `max_tokens`.
+    pub fn encode_check_limit(
+        &self,
+        text: &str,
+        allowed_special: &HashSet<&str>,
+        max_tokens: usize,
+    ) -> (Vec<Rank>, bool) {
+        let (tokens, _) = self.tokenizer.encode(text, allowed_special);
+        let over = tokens.len() > max_tokens;
+        (tokens, over)
+    }
+
+    /// Encode `text` as ordinary text and map each pre-tokenizer word to the
+    /// range of tokens it produced. The ranges are contiguous and cover all tokens;
+    /// `char_start` is the word's char offset in `text`.
+    pub fn word_token_alignment(&self, text: &str) -> Vec<WordTokenAlignment> {
+        let mut out = Vec::new();
+        // running (byte, char) offset, advanced to each piece's start so text the
+        // pre-tokenizer skips over cannot shift later `char_start`s
+        let (mut byte_pos, mut char_pos) = (0, 0);
+        let mut token_start = 0;
+        for (range, tokens) in self.tokenizer.encode_ordinary_by_piece(text) {
+            char_pos += text[byte_pos..range.start].chars().count();
+            byte_pos = range.start;
+            out.push(WordTokenAlignment {
+                word: text[range].to_string(),
+                char_start: char_pos,
+                token_start,
+                token_end: token_start + tokens.len(),
+            });
+            token_start += tokens.len();
+        }
+        out
+    }
+}
+
+/// Special tokens added and removed when moving between two encodings, see
+/// [`HarmonyEncoding::special_tokens_diff`]. All lists are sorted.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct SpecialTokensDiff {
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+    pub common: Vec<String>,
+}
+
+/// A pre-tokenizer word of some text and the tokens it encodes to, see
+/// [`HarmonyEncoding::word_token_alignment`]. `char_start` counts chars, and
+/// `token_end` is exclusive.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WordTokenAlignment {
+    pub word: String,
+    pub char_start: usize,
+    pub token_start: usize,
+    pub token_end: usize,
+}
+
+/// Upper bound on the cells of the LCS table built by [`line_diff`].
+const MAX_LINE_DIFF_CELLS: usize = 1 << 20;
+
+/// Minimal LCS-based line diff, returning only the changed lines prefixed with
+/// `- ` (only in `a`) or `+ ` (only in `b`). The common prefix and suffix are
+/// skipped; if what remains is still too large to compare line by line, a single
+/// `~ N lines differ` summary is returned instead.
+pub(crate) fn line_diff(a: &str, b: &str) -> Vec<String> {
+    let a: Vec<&str> = a.split('\n').collect();
+    let b: Vec<&str> = b.split('\n').collect();
+    let prefix = a.iter().zip(&b).take_while(|(x, y)| x == y).count();
+    let (a, b) = (&a[prefix..], &b[prefix..]);
+    let suffix = a
+        .iter()
+        .rev()
+        .zip(b.iter().rev())
+        .take_while(|(x, y)| x == y)
+        .count();
+    let (a, b) = (&a[..a.len() - suffix], &b[..b.len() - suffix]);
+    if (a.len() + 1).saturating_mul(b.len() + 1) > MAX_LINE_DIFF_CELLS {
+        return vec![format!("~ {} lines differ", a.len() + b.len())];
+    }
+    // lcs[i][j] is the length of the longest common subsequence of a[i..] and b[j..]
+    let mut lcs = vec![vec![0usize; b.len() + 1]; a.len() + 1];
+    for i in (0..a.len()).rev() {
+        for j in (0..b.len()).rev() {
+            lcs[i][j] = if a[i] == b[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+    let (mut i, mut j) = (0, 0);
+    let mut out = Vec::new();
+    while i < a.len() || j < b.len() {
+        if i < a.len() && j < b.len() && a[i] == b[j] {
+            i += 1;
+            j += 1;
+        } else if j == b.len() || (i < a.len() && lcs[i + 1][j] >= lcs[i][j + 1]) {
+            out.push(format!("- {}", a[i]));
+            i += 1;
+        } else {
+            out.push(format!("+ {}", b[j]));
+            j += 1;
+        }
+    }
+    out
+}
+
 // Rendering helper methods
 impl HarmonyEncoding {
+    /// Check that `config` can be applied to the given conversation messages.
+    fn validate_render_config(
+        messages: &[&Message],
+        config: &RenderConversationConfig,
+    ) -> anyhow::Result<()> {
+        if let Some(max_messages) = config.max_messages {
+            anyhow::ensure!(
+                messages.len() <= max_messages,
+                "conversation has {} messages, which exceeds the configured maximum of {}",
+                messages.len(),
+                max_messages
+            );
+        }
+        let valid_channels = messages.iter().find_map(|msg| {
+            msg.content.iter().find_map(|c| match c {
+                Content::SystemContent(sys) => sys
+                    .channel_config
+                    .as_ref()
+                    .filter(|cc| !cc.valid_channels.is_empty())
+                    .map(|cc| &cc.valid_channels),
+                _ => None,
+            })
+        });
+        for (&idx, channel) in &config.channel_overrides {
+            anyhow::ensure!(
+                idx < messages.len(),
+                "channel override index {} is out of range for a conversation of {} messages",
+                idx,
+                messages.len()
+            );
+            anyhow::ensure!(
+                !channel.is_empty() && !channel.contains(|c: char| c.is_whitespace() || c == '<'),
+                "invalid channel override {:?} for message {}",
+                channel,
+                idx
+            );
+            if let Some(valid_channels) = valid_channels {
+                anyhow::ensure!(
+                    valid_channels.contains(channel),
+                    "channel override {:?} for message {} is not one of the valid channels {:?}",
+                    channel,
+                    idx,
+                    valid_channels
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// Whether `config` asks to skip empty messages and `msg` has no content
+    /// besides whitespace-only text.
+    fn is_skipped_empty_message(msg: &Message, config: Option<&RenderConversationConfig>) -> bool {
+        config.is_some_and(|c| c.skip_empty_messages == Some(true))
+            && msg.content.iter().all(|c| match c {
+                Content::Text(text) => text.text.trim().is_empty(),
+                _ => false,
+            })
+    }
+
+    /// Apply the per-render adjustments requested by `config` to the message at
+    /// index `idx`, borrowing it unchanged when there is nothing to adjust.
+    fn apply_render_config<'m>(
+        idx: usize,
+        message: &'m Message,
+        config: Option<&RenderConversationConfig>,
+    ) -> Cow<'m, Message> {
+        let mut message = Cow::Borrowed(message);
+        let Some(config) = config else {
+            return message;
+        };
+        if let Some(system_author) = &config.system_author {
+            // an explicit author name on the message takes precedence
+            if message.author.role == Role::System && message.author.name.is_none() {
+                message.to_mut().author.name = Some(system_author.clone());
+            }
+        }
+        if let Some(channel) = config.channel_overrides.get(&idx) {
+            message.to_mut().channel = Some(channel.clone());
+        }
+        message
+    }
+
     fn mapped_format_token(&self, t: FormattingToken) -> Option<&str> {
         self.format_token_mapping.get(&t).map(|s| s.as_str())
     }
@@ -415,6 +776,216 @@ impl HarmonyEncoding {
         Ok(parser.into_messages())
     }
 
+    /// Whether `tokens` ends cleanly: after a complete message, or after the bare
+    /// `<|start|>{role}` header that opens the next turn (as rendered for completion).
+    ///
+    /// Streams ending mid-header or mid-content, as well as malformed streams,
+    /// are not at a boundary.
+    pub fn ends_at_message_boundary<I>(&self, tokens: I) -> anyhow::Result<bool>
+    where
+        I: IntoIterator<Item = Rank>,
+    {
+        let mut parser = StreamableParser::new(self.clone(), None)?;
+        for token in tokens {
+            if parser.process(token).is_err() {
+                return Ok(false);
+            }
+        }
+        Ok(match &parser.state {
+            StreamState::ExpectStart => true,
+            StreamState::Header { header_tokens } => self
+                .tokenizer
+                .decode_utf8(header_tokens)
+                .is_ok_and(|header| Role::try_from(header.as_str()).is_ok()),
+            StreamState::Content { .. } => false,
+        })
+    }
+
+    /// Parse completion tokens and concatenate the text content of all messages sent
+    /// to `channel`. Returns `None` when no message uses that channel.
+    pub fn extract_channel_content<I>(
+        &self,
+        tokens: I,
+        role: Option<Role>,
+        channel: &str,
+    ) -> anyhow::Result<Option<String>>
+    where
+        I: IntoIterator<Item = Rank>,
+    {
+        let messages = self.parse_messages_from_completion_tokens(tokens, role)?;
+        let mut found = false;
+        let mut out = String::new();
+        for message in messages
+            .iter()
+            .filter(|m| m.channel.as_deref() == Some(channel))
+        {
+            found = true;
+            for content in &message.content {
+                if let Content::Text(text) = content {
+                    out.push_str(&text.text);
+                }
+            }
+        }
+        Ok(found.then_some(out))
+    }
+
+    /// Parse completion tokens and report every assistant tool call together with
+    /// the half-open range of input token indices holding its arguments.
+    pub fn parse_tool_calls_with_spans<I>(
+        &self,
+        tokens: I,
+        role: Option<Role>,
+    ) -> anyhow::Result<Vec<ToolCallSpan>>
+    where
+        I: IntoIterator<Item = Rank>,
+    {
+        let mut parser = StreamableParser::new(self.clone(), role)?;
+        let mut spans = Vec::new();
+        let mut content_start: Option<usize> = None;
+        let mut num_tokens = 0;
+        for (idx, token) in tokens.into_iter().enumerate() {
+            let num_messages = parser.messages.len();
+            parser.process(token)?;
+            num_tokens = idx + 1;
+            if parser.messages.len() > num_messages {
+                // the stop token at `idx` closed the message
+                Self::push_tool_call_span(&parser, content_start.take(), idx, &mut spans);
+            } else if content_start.is_none() && matches!(parser.state, StreamState::Content { .. })
+            {
+                content_start = Some(idx + 1);
+            }
+        }
+        let num_messages = parser.messages.len();
+        parser.process_eos()?;
+        if parser.messages.len() > num_messages {
+            Self::push_tool_call_span(&parser, content_start.take(), num_tokens, &mut spans);
+        }
+        Ok(spans)
+    }
+
+    fn push_tool_call_span(
+        parser: &StreamableParser,
+        content_start: Option<usize>,
+        content_end: usize,
+        spans: &mut Vec<ToolCallSpan>,
+    ) {
+        let message_index = parser.messages.len() - 1;
+        let message = &parser.messages[message_index];
+        let Some(recipient) = message.recipient.as_ref() else {
+            return;
+        };
+        if message.author.role != Role::Assistant || recipient == "all" {
+            return;
+        }
+        let arguments = message
+            .content
+            .iter()
+            .filter_map(|c| match c {
+                Content::Text(t) => Some(t.text.as_str()),
+                _ => None,
+            })
+            .collect();
+        spans.push(ToolCallSpan {
+            message_index,
+            recipient: recipient.clone(),
+            channel: message.channel.clone(),
+            content_type: message.content_type.clone(),
+            arguments,
+            arguments_token_range: (content_start.unwrap_or(content_end), content_end),
+        });
+    }
+
+    /// Best-effort repair of a malformed completion token stream.
+    ///
+    /// Two heuristics are applied, each recorded in the returned repairs:
+    /// - an assistant header without a channel gets `<|channel|>commentary` when it
+    ///   addresses a recipient and `<|channel|>final` otherwise, inserted just before
+    ///   `<|message|>`;
+    /// - a stop token directly following another stop token is dropped.
+    ///
+    /// A stream that does not begin with `<|start|>` is assumed to continue an
+    /// assistant header. Well-formed streams are returned unchanged.
+    pub fn repair_stream<I>(&self, tokens: I) -> anyhow::Result<RepairedStream>
+    where
+        I: IntoIterator<Item = Rank>,
+    {
+        #[derive(Clone, Copy)]
+        enum RepairState {
+            Outside,
+            Header { start: usize, assistant: bool },
+            Content,
+        }
+
+        let start = self.render_formatting_token(FormattingToken::Start)?;
+        let message = self.render_formatting_token(FormattingToken::Message)?;
+        let channel = self.render_formatting_token(FormattingToken::Channel)?;
+        let stop_tokens = self.stop_tokens()?;
+
+        let tokens: Vec<Rank> = tokens.into_iter().collect();
+        let mut out = Vec::with_capacity(tokens.len());
+        let mut repairs = Vec::new();
+        let mut state = match tokens.first() {
+            Some(&first) if first != start => RepairState::Header {
+                start: 0,
+                assistant: true,
+            },
+            _ => RepairState::Outside,
+        };
+        for (idx, token) in tokens.into_iter().enumerate() {
+            match state {
+                RepairState::Outside => {
+                    if token == start {
+                        state = RepairState::Header {
+                            start: out.len() + 1,
+                            assistant: false,
+                        };
+                    } else if stop_tokens.contains(&token)
+                        && out.last().is_some_and(|t| stop_tokens.contains(t))
+                    {
+                        repairs.push(StreamRepair::DroppedDuplicateStop { index: idx, token });
+                        continue;
+                    }
+                }
+                RepairState::Header {
+                    start: header_start,
+                    assistant,
+                } => {
+                    if token == message {
+                        let header = &out[header_start..];
+                        let decoded = self.tokenizer.decode_utf8(header).ok();
+                        let is_assistant = assistant
+                            || decoded
+                                .as_deref()
+                                .is_some_and(|h| h.starts_with(Role::Assistant.as_str()));
+                        if is_assistant && !header.contains(&channel) {
+                            let has_recipient =
+                                decoded.as_deref().is_some_and(|h| h.contains("to="));
+                            let default_channel =
+                                if has_recipient { "commentary" } else { "final" };
+                            out.push(channel);
+                            self.render_text_into(default_channel, &mut out)?;
+                            repairs.push(StreamRepair::InsertedChannel {
+                                index: idx,
+                                channel: default_channel.to_string(),
+                            });
+                        }
+                        state = RepairState::Content;
+                    }
+                }
+                RepairState::Content => {
+                    if stop_tokens.contains(&token) {
+                        state = RepairState::Outside;
+                    }
+                }
+            }
+            out.push(token);
+        }
+        Ok(RepairedStream {
+            tokens: out,
+            repairs,
+        })
+    }
+
     /// Helper to convert a JSON schema (OpenAPI style) to a TypeScript type definition.
     fn json_schema_to_typescript(schema: &serde_json::Value, indent: &str) -> String {
         // Helper to check if this schema is an enum
@@ -806,6 +1377,39 @@ impl HarmonyEncoding {
     }
 }
 
+/// A tool call parsed from completion tokens along with the token span of its arguments.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ToolCallSpan {
+    /// Index of the tool call among all messages parsed from the tokens.
+    pub message_index: usize,
+    pub recipient: String,
+    pub channel: Option<String>,
+    pub content_type: Option<String>,
+    /// Decoded arguments, i.e. the message content.
+    pub arguments: String,
+    /// Half-open `[start, end)` range of input token indices decoding to `arguments`.
+    pub arguments_token_range: (usize, usize),
+}
+
+/// A single fix applied by [`HarmonyEncoding::repair_stream`]. `index` refers to the
+/// position in the input token stream.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum StreamRepair {
+    /// A channel was inserted into an assistant header right before the
+    /// `<|message|>` token at `index`.
+    InsertedChannel { index: usize, channel: String },
+    /// The stop token at `index` directly followed another stop token and was dropped.
+    DroppedDuplicateStop { index: usize, token: Rank },
+}
+
+/// Result of [`HarmonyEncoding::repair_stream`].
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct RepairedStream {
+    pub tokens: Vec<Rank>,
+    pub repairs: Vec<StreamRepair>,
+}
+
 #[derive(Clone, Copy, Debug, Default, Serialize, Deserialize)]
 pub struct RenderOptions {
     pub conversation_has_function_tools: bool,
@@ -1371,6 +1975,30 @@ impl StreamableParser {
         }
     }
 
+    /// The in-progress message as JSON, in the same shape as a serialized
+    /// [`Message`], with the content decoded so far.
+    ///
+    /// Returns `null` when no message is in progress or its role is not known yet.
+    pub fn current_message_partial(&self) -> anyhow::Result<serde_json::Value> {
+        let message = match &self.state {
+            StreamState::Content { header, .. } => Message {
+                author: header.author.clone(),
+                recipient: header.recipient.clone(),
+                channel: header.channel.clone(),
+                content_type: header.content_type.clone(),
+                content: vec![Content::Text(TextContent {
+                    text: self.current_content()?,
+                })],
+            },
+            StreamState::Header { .. } => match &self.next_role {
+                Some(role) => Message::from_role_and_content(role.clone(), ""),
+                None => return Ok(serde_json::Value::Null),
+            },
+            StreamState::ExpectStart => return Ok(serde_json::Value::Null),
+        };
+        Ok(serde_json::to_value(message)?)
+    }
+
     /// Role of the current message if it has been parsed.
     pub fn current_role(&self) -> Option<Role> {
         match &self.state {
@@ -1437,6 +2065,44 @@ impl StreamableParser {
         Ok(serde_json::to_string(&serializable)?)
     }
 
+    /// Static description of the parser state machine for documentation tools.
+    ///
+    /// States are named after [`StreamState`] variants; transitions are keyed by
+    /// token category. Transitions marked with `"error": true` make the parser fail.
+    /// A parser created with a role starts in `Header` instead of `ExpectStart`.
+    pub fn transition_table() -> serde_json::Value {
+        serde_json::json!({
+            "initial_state": "ExpectStart",
+            "states": [
+                {"name": "ExpectStart", "description": "waiting for the start of the next message"},
+                {"name": "Header", "description": "collecting header tokens (role, recipient, channel, content type)"},
+                {"name": "Content", "description": "collecting content tokens of the current message"},
+            ],
+            "token_categories": [
+                {"name": "start", "tokens": [FormattingToken::Start.as_str()]},
+                {"name": "message", "tokens": [FormattingToken::Message.as_str()]},
+                {"name": "stop", "tokens": [
+                    FormattingToken::EndMessage.as_str(),
+                    FormattingToken::EndMessageDoneSampling.as_str(),
+                    FormattingToken::EndMessageAssistantToTool.as_str(),
+                ]},
+                {"name": "eos", "tokens": []},
+                {"name": "other", "tokens": []},
+            ],
+            "transitions": [
+                {"from": "ExpectStart", "on": "start", "to": "Header"},
+                {"from": "ExpectStart", "on": "eos", "to": "ExpectStart"},
+                {"from": "ExpectStart", "on": "other", "to": null, "error": true},
+                {"from": "Header", "on": "message", "to": "Content"},
+                {"from": "Header", "on": "other", "to": "Header"},
+                {"from": "Header", "on": "eos", "to": null, "error": true},
+                {"from": "Content", "on": "stop", "to": "ExpectStart", "emits_message": true},
+                {"from": "Content", "on": "eos", "to": "ExpectStart", "emits_message": true},
+                {"from": "Content", "on": "other", "to": "Content"},
+            ],
+        })
+    }
+
     /// Return the current recipient if known.
     pub fn current_recipient(&self) -> Option<String> {
         match &self.state {
@@ -1456,14 +2122,34 @@ impl StreamableParser {
 
 // Add config struct for rendering
 #[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
 pub struct RenderConversationConfig {
     pub auto_drop_analysis: bool,
+    /// Name of the system persona rendered in the system header (`system:NAME`).
+    /// Only applied to system messages that do not already carry an author name.
+    #[serde(default)]
+    pub system_author: Option<String>,
+    /// Channels to render instead of the messages' own, keyed by message index.
+    /// Indices must be in range and channels must be valid for the conversation.
+    #[serde(default)]
+    pub channel_overrides: HashMap<usize, String>,
+    /// Refuse to render conversations with more messages than this. Unbounded by default.
+    #[serde(default)]
+    pub max_messages: Option<usize>,
+    /// Drop messages whose content is empty after trimming instead of rendering
+    /// a bare header for them. Off by default.
+    #[serde(default)]
+    pub skip_empty_messages: Option<bool>,
 }
 
 impl Default for RenderConversationConfig {
     fn default() -> Self {
         Self {
             auto_drop_analysis: true,
+            system_author: None,
+            channel_overrides: HashMap::new(),
+            max_messages: None,
+            skip_empty_messages: None,
         }
     }
 }
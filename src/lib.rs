@@ -6,7 +6,10 @@ mod registry;
 mod tiktoken;
 pub mod tiktoken_ext;
 
-pub use encoding::{HarmonyEncoding, StreamableParser};
+pub use encoding::{
+    HarmonyEncoding, RepairedStream, SpecialTokensDiff, StreamRepair, StreamableParser,
+    ToolCallSpan, WordTokenAlignment,
+};
 pub use registry::load_harmony_encoding;
 pub use registry::HarmonyEncodingName;
 
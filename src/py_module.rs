@@ -50,6 +50,48 @@ pub enum PyStreamState {
     Content,
 }
 
+/// Build a `RenderConversationConfig` from an optional Python config dict.
+/// Missing keys keep their defaults.
+fn render_config_from_dict(
+    config: Option<Bound<'_, PyDict>>,
+) -> PyResult<Option<crate::encoding::RenderConversationConfig>> {
+    let Some(cfg_dict) = config else {
+        return Ok(None);
+    };
+    let auto_drop_analysis = cfg_dict
+        .get_item("auto_drop_analysis")?
+        .and_then(|v| v.extract().ok())
+        .unwrap_or(true);
+    let system_author = cfg_dict
+        .get_item("system_author")?
+        .map(|v| v.extract::<Option<String>>())
+        .transpose()?
+        .flatten();
+    let channel_overrides = cfg_dict
+        .get_item("channel_overrides")?
+        .map(|v| v.extract::<Option<std::collections::HashMap<usize, String>>>())
+        .transpose()?
+        .flatten()
+        .unwrap_or_default();
+    let max_messages = cfg_dict
+        .get_item("max_messages")?
+        .map(|v| v.extract::<Option<usize>>())
+        .transpose()?
+        .flatten();
+    let skip_empty_messages = cfg_dict
+        .get_item("skip_empty_messages")?
+        .map(|v| v.extract::<Option<bool>>())
+        .transpose()?
+        .flatten();
+    Ok(Some(crate::encoding::RenderConversationConfig {
+        auto_drop_analysis,
+        system_author,
+        channel_overrides,
+        max_messages,
+        skip_empty_messages,
+    }))
+}
+
 #[pymethods]
 impl PyHarmonyEncoding {
     /// Create a new `HarmonyEncoding` by name.
@@ -78,7 +120,8 @@ impl PyHarmonyEncoding {
     /// next_turn_role : str
     ///     The role of the *next* turn (e.g. "assistant").
     /// config : dict (optional)
-    ///     Optional config dict. Only supports 'auto_drop_analysis' (bool).
+    ///     Optional config dict with the `RenderConversationConfig` keys
+    ///     ('auto_drop_analysis', 'system_author', ...).
     ///
     /// Returns
     /// -------
@@ -106,15 +149,7 @@ impl PyHarmonyEncoding {
         })?;
 
         // Parse config
-        let rust_config = if let Some(cfg_dict) = config {
-            let auto_drop_analysis = cfg_dict
-                .get_item("auto_drop_analysis")?
-                .and_then(|v| v.extract().ok())
-                .unwrap_or(true);
-            Some(crate::encoding::RenderConversationConfig { auto_drop_analysis })
-        } else {
-            None
-        };
+        let rust_config = render_config_from_dict(config)?;
 
         self.inner
             .render_conversation_for_completion(&conversation, role, rust_config.as_ref())
@@ -134,15 +169,7 @@ impl PyHarmonyEncoding {
                 ))
             })?;
 
-        let rust_config = if let Some(cfg_dict) = config {
-            let auto_drop_analysis = cfg_dict
-                .get_item("auto_drop_analysis")?
-                .and_then(|v| v.extract().ok())
-                .unwrap_or(true);
-            Some(crate::encoding::RenderConversationConfig { auto_drop_analysis })
-        } else {
-            None
-        };
+        let rust_config = render_config_from_dict(config)?;
 
         self.inner
             .render_conversation(&conversation, rust_config.as_ref())
@@ -162,15 +189,7 @@ impl PyHarmonyEncoding {
                 ))
             })?;
 
-        let rust_config = if let Some(cfg_dict) = config {
-            let auto_drop_analysis = cfg_dict
-                .get_item("auto_drop_analysis")?
-                .and_then(|v| v.extract().ok())
-                .unwrap_or(true);
-            Some(crate::encoding::RenderConversationConfig { auto_drop_analysis })
-        } else {
-            None
-        };
+        let rust_config = render_config_from_dict(config)?;
 
         self.inner
             .render_conversation_for_training(&conversation, rust_config.as_ref())
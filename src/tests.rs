@@ -433,6 +433,7 @@ fn test_dropping_cot_by_default() {
             Role::Assistant,
             Some(&crate::encoding::RenderConversationConfig {
                 auto_drop_analysis: true,
+                ..Default::default()
             }),
         )
         .unwrap();
@@ -469,6 +470,7 @@ fn test_does_not_drop_if_ongoing_analysis() {
             Role::Assistant,
             Some(&crate::encoding::RenderConversationConfig {
                 auto_drop_analysis: true,
+                ..Default::default()
             }),
         )
         .unwrap();
@@ -499,6 +501,7 @@ fn test_preserve_cot() {
             Role::Assistant,
             Some(&crate::encoding::RenderConversationConfig {
                 auto_drop_analysis: false,
+                ..Default::default()
             }),
         )
         .unwrap();
@@ -709,3 +712,619 @@ fn test_tool_call_with_channel_before_recipient_and_constrain_adjacent() {
     .with_content_type("<|constrain|>json")];
     assert_eq!(parsed, expected);
 }
+
+#[test]
+fn test_conversation_referenced_tools() {
+    let convo = Conversation::from_messages([
+        Message::from_role_and_content(Role::User, "What is the weather in SF and Tokyo?"),
+        Message::from_role_and_content(Role::Assistant, "{\"location\": \"San Francisco\"}")
+            .with_channel("commentary")
+            .with_recipient("functions.lookup_weather")
+            .with_content_type("<|constrain|>json"),
+        Message::from_author_and_content(
+            Author::new(Role::Tool, "functions.lookup_weather"),
+            "{\"temperature\": 20, \"description\": \"sunny\"}",
+        ),
+        Message::from_role_and_content(Role::Assistant, "{\"query\": \"Tokyo weather\"}")
+            .with_channel("commentary")
+            .with_recipient("browser.search"),
+        Message::from_role_and_content(Role::Assistant, "{\"location\": \"Tokyo\"}")
+            .with_channel("commentary")
+            .with_recipient("functions.lookup_weather"),
+        Message::from_role_and_content(Role::Assistant, "It is sunny in both.")
+            .with_channel("final"),
+    ]);
+    let tools: Vec<String> = convo.referenced_tools().into_iter().collect();
+    assert_eq!(tools, vec!["browser.search", "functions.lookup_weather"]);
+}
+
+#[test]
+fn test_render_with_system_author() {
+    let encoding = load_harmony_encoding(HarmonyEncodingName::HarmonyGptOss).unwrap();
+    let convo = Conversation::from_messages([
+        Message::from_role_and_content(Role::System, SystemContent::new()),
+        Message::from_role_and_content(Role::User, "What is 2 + 2?"),
+    ]);
+
+    let config = crate::encoding::RenderConversationConfig {
+        system_author: Some("orchestrator".to_string()),
+        ..Default::default()
+    };
+    let tokens = encoding
+        .render_conversation_for_completion(&convo, Role::Assistant, Some(&config))
+        .unwrap();
+    let decoded = encoding.tokenizer.decode_utf8(&tokens).unwrap();
+    assert!(
+        decoded.starts_with("<|start|>system:orchestrator<|message|>"),
+        "system author missing from header: {decoded}"
+    );
+
+    let default_tokens = encoding
+        .render_conversation_for_completion(&convo, Role::Assistant, None)
+        .unwrap();
+    let default_config_tokens = encoding
+        .render_conversation_for_completion(
+            &convo,
+            Role::Assistant,
+            Some(&crate::encoding::RenderConversationConfig::default()),
+        )
+        .unwrap();
+    assert_eq!(default_tokens, default_config_tokens);
+    let decoded = encoding.tokenizer.decode_utf8(&default_tokens).unwrap();
+    assert!(decoded.starts_with("<|start|>system<|message|>"));
+}
+
+#[test]
+fn test_terminator_tokens_match_render_tail() {
+    let encoding = load_harmony_encoding(HarmonyEncodingName::HarmonyGptOss).unwrap();
+    let convo = Conversation::from_messages([
+        Message::from_role_and_content(Role::User, "What is 2 + 2?"),
+        Message::from_role_and_content(Role::Assistant, "2 + 2 equals 4.").with_channel("final"),
+    ]);
+
+    let end = encoding.terminator_tokens("end").unwrap();
+    let rendered = encoding.render_conversation(&convo, None).unwrap();
+    assert_eq!(&rendered[rendered.len() - end.len()..], &end[..]);
+
+    let ret = encoding.terminator_tokens("return").unwrap();
+    let rendered = encoding
+        .render_conversation_for_training(&convo, None)
+        .unwrap();
+    assert_eq!(&rendered[rendered.len() - ret.len()..], &ret[..]);
+
+    assert!(encoding.terminator_tokens("stop").is_err());
+}
+
+#[test]
+fn test_parse_tool_calls_with_spans() {
+    let encoding = load_harmony_encoding(HarmonyEncodingName::HarmonyGptOss).unwrap();
+    let text = "<|start|>assistant<|channel|>analysis<|message|>Need the weather.<|end|><|start|>assistant<|channel|>commentary to=functions.get_weather<|constrain|>json<|message|>{\"latitude\":48.8566,\"longitude\":2.3522}<|call|>";
+    let tokens = encoding.tokenizer().encode_with_special_tokens(text);
+    let spans = encoding
+        .parse_tool_calls_with_spans(tokens.iter().copied(), None)
+        .unwrap();
+    assert_eq!(spans.len(), 1);
+    let span = &spans[0];
+    assert_eq!(span.message_index, 1);
+    assert_eq!(span.recipient, "functions.get_weather");
+    assert_eq!(
+        span.arguments,
+        "{\"latitude\":48.8566,\"longitude\":2.3522}"
+    );
+    let (start, end) = span.arguments_token_range;
+    assert_eq!(
+        encoding.tokenizer.decode_utf8(&tokens[start..end]).unwrap(),
+        span.arguments
+    );
+}
+
+#[test]
+fn test_example_conversation_roundtrip() {
+    let encoding = load_harmony_encoding(HarmonyEncodingName::HarmonyGptOss).unwrap();
+    let convo = Conversation::example();
+
+    let json = serde_json::to_string(&convo).unwrap();
+    let from_json: Conversation = serde_json::from_str(&json).unwrap();
+    assert_eq!(from_json, convo);
+
+    let tokens = encoding.render_conversation(&convo, None).unwrap();
+    let parsed = encoding
+        .parse_messages_from_completion_tokens(tokens, None)
+        .unwrap();
+    assert_eq!(parsed.len(), convo.messages.len());
+    assert_eq!(parsed[1..], convo.messages[1..]);
+}
+
+#[test]
+fn test_tool_token_cost() {
+    let encoding = load_harmony_encoding(HarmonyEncodingName::HarmonyGptOss).unwrap();
+    let existing = vec![ToolDescription::new(
+        "get_location",
+        "Gets the location of the user.",
+        None,
+    )];
+    let new_tool = ToolDescription::new(
+        "get_current_weather",
+        "Gets the current weather in the provided location.",
+        Some(json!({
+            "type": "object",
+            "properties": {
+                "location": {"type": "string"}
+            },
+            "required": ["location"]
+        })),
+    );
+
+    let render_len = |tools: Vec<ToolDescription>| {
+        let dev = DeveloperContent::new().with_function_tools(tools);
+        let convo =
+            Conversation::from_messages([Message::from_role_and_content(Role::Developer, dev)]);
+        encoding.render_conversation(&convo, None).unwrap().len() as i64
+    };
+    let expected =
+        render_len(vec![existing[0].clone(), new_tool.clone()]) - render_len(existing.clone());
+
+    let delta = encoding
+        .tool_token_cost(&existing, &new_tool, None)
+        .unwrap();
+    assert_eq!(delta, expected);
+    assert!(delta > 0);
+
+    // settings aimed at the caller's conversation don't apply to the tool render
+    let config = crate::encoding::RenderConversationConfig {
+        channel_overrides: [(3, "commentary".to_string())].into_iter().collect(),
+        max_messages: Some(0),
+        ..Default::default()
+    };
+    let delta = encoding
+        .tool_token_cost(&existing, &new_tool, Some(&config))
+        .unwrap();
+    assert_eq!(delta, expected);
+}
+
+#[test]
+fn test_repair_stream_missing_channel() {
+    let encoding = load_harmony_encoding(HarmonyEncodingName::HarmonyGptOss).unwrap();
+    let tokens = encoding
+        .tokenizer()
+        .encode_with_special_tokens("<|start|>assistant<|message|>2 + 2 equals 4.<|end|><|end|>");
+    let repaired = encoding.repair_stream(tokens).unwrap();
+    let expected = encoding.tokenizer().encode_with_special_tokens(
+        "<|start|>assistant<|channel|>final<|message|>2 + 2 equals 4.<|end|>",
+    );
+    assert_tokens_eq(&encoding.tokenizer, &expected, &repaired.tokens);
+    assert_eq!(repaired.repairs.len(), 2);
+    assert!(matches!(
+        &repaired.repairs[0],
+        crate::encoding::StreamRepair::InsertedChannel { channel, .. } if channel == "final"
+    ));
+    assert!(matches!(
+        repaired.repairs[1],
+        crate::encoding::StreamRepair::DroppedDuplicateStop { .. }
+    ));
+
+    let parsed = encoding
+        .parse_messages_from_completion_tokens(repaired.tokens, None)
+        .unwrap();
+    assert_eq!(
+        parsed,
+        vec![
+            Message::from_role_and_content(Role::Assistant, "2 + 2 equals 4.")
+                .with_channel("final")
+        ]
+    );
+}
+
+#[test]
+fn test_repair_stream_clean_stream_unchanged() {
+    let encoding = load_harmony_encoding(HarmonyEncodingName::HarmonyGptOss).unwrap();
+    let tokens = encoding.tokenizer().encode_with_special_tokens(
+        "<|start|>assistant<|channel|>analysis<|message|>Simple arithmetic.<|end|><|start|>assistant<|channel|>commentary to=functions.get_weather<|constrain|>json<|message|>{\"location\": \"Tokyo\"}<|call|>",
+    );
+    let repaired = encoding.repair_stream(tokens.iter().copied()).unwrap();
+    assert_eq!(repaired.tokens, tokens);
+    assert!(repaired.repairs.is_empty());
+}
+
+#[test]
+fn test_parser_transition_table() {
+    let table = StreamableParser::transition_table();
+    let states: Vec<&str> = table["states"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|s| s["name"].as_str().unwrap())
+        .collect();
+    assert_eq!(states, vec!["ExpectStart", "Header", "Content"]);
+    assert_eq!(table["initial_state"], "ExpectStart");
+
+    let transitions = table["transitions"].as_array().unwrap();
+    let has_transition = |from: &str, on: &str, to: &str| {
+        transitions
+            .iter()
+            .any(|t| t["from"] == from && t["on"] == on && t["to"] == to)
+    };
+    assert!(has_transition("ExpectStart", "start", "Header"));
+    assert!(has_transition("Header", "message", "Content"));
+    assert!(has_transition("Content", "stop", "ExpectStart"));
+
+    let stop_tokens = table["token_categories"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|c| c["name"] == "stop")
+        .unwrap();
+    assert_eq!(
+        stop_tokens["tokens"],
+        json!(["<|end|>", "<|return|>", "<|call|>"])
+    );
+}
+
+#[test]
+fn test_render_with_channel_overrides() {
+    let encoding = load_harmony_encoding(HarmonyEncodingName::HarmonyGptOss).unwrap();
+    let convo = Conversation::from_messages([
+        Message::from_role_and_content(Role::User, "What is 2 + 2?"),
+        Message::from_role_and_content(Role::Assistant, "2 + 2 equals 4.").with_channel("final"),
+    ]);
+
+    let config = crate::encoding::RenderConversationConfig {
+        channel_overrides: [(1, "commentary".to_string())].into_iter().collect(),
+        ..Default::default()
+    };
+    let tokens = encoding.render_conversation(&convo, Some(&config)).unwrap();
+    let decoded = encoding.tokenizer.decode_utf8(&tokens).unwrap();
+    assert_eq!(
+        decoded,
+        "<|start|>user<|message|>What is 2 + 2?<|end|><|start|>assistant<|channel|>commentary<|message|>2 + 2 equals 4.<|end|>"
+    );
+    // the conversation itself is left untouched
+    assert_eq!(convo.messages[1].channel.as_deref(), Some("final"));
+
+    let config = crate::encoding::RenderConversationConfig {
+        channel_overrides: [(2, "commentary".to_string())].into_iter().collect(),
+        ..Default::default()
+    };
+    let err = encoding
+        .render_conversation(&convo, Some(&config))
+        .unwrap_err();
+    assert!(err.to_string().contains("out of range"), "{err}");
+
+    let config = crate::encoding::RenderConversationConfig {
+        channel_overrides: [(1, "bad channel".to_string())].into_iter().collect(),
+        ..Default::default()
+    };
+    assert!(encoding.render_conversation(&convo, Some(&config)).is_err());
+
+    // invalid indices arriving as JSON are rejected rather than ignored
+    let config: crate::encoding::RenderConversationConfig =
+        serde_json::from_str(r#"{"channel_overrides": {"5": "commentary"}}"#).unwrap();
+    let err = encoding
+        .render_conversation(&convo, Some(&config))
+        .unwrap_err();
+    assert!(err.to_string().contains("out of range"), "{err}");
+    assert!(
+        serde_json::from_str::<crate::encoding::RenderConversationConfig>(
+            r#"{"channel_overrides": {"-1": "commentary"}}"#
+        )
+        .is_err()
+    );
+}
+
+#[test]
+fn test_extract_commentary() {
+    let encoding = load_harmony_encoding(HarmonyEncodingName::HarmonyGptOss).unwrap();
+    let text = "<|channel|>analysis<|message|>Need the weather.<|end|><|start|>assistant<|channel|>commentary<|message|>Checking the weather.<|end|><|start|>assistant<|channel|>commentary to=functions.get_weather<|constrain|>json<|message|>{\"location\": \"Tokyo\"}<|call|>";
+    let tokens = encoding.tokenizer().encode_with_special_tokens(text);
+    let commentary = encoding
+        .extract_channel_content(tokens.iter().copied(), Some(Role::Assistant), "commentary")
+        .unwrap();
+    assert_eq!(
+        commentary.as_deref(),
+        Some("Checking the weather.{\"location\": \"Tokyo\"}")
+    );
+
+    let tokens = encoding
+        .tokenizer()
+        .encode_with_special_tokens("<|channel|>final<|message|>2 + 2 equals 4.<|return|>");
+    let commentary = encoding
+        .extract_channel_content(tokens, Some(Role::Assistant), "commentary")
+        .unwrap();
+    assert_eq!(commentary, None);
+}
+
+#[test]
+fn test_ends_at_message_boundary() {
+    let encoding = load_harmony_encoding(HarmonyEncodingName::HarmonyGptOss).unwrap();
+    let convo = Conversation::from_messages([
+        Message::from_role_and_content(Role::System, SystemContent::new()),
+        Message::from_role_and_content(Role::User, "What is 2 + 2?"),
+    ]);
+
+    let rendered = encoding.render_conversation(&convo, None).unwrap();
+    assert!(encoding
+        .ends_at_message_boundary(rendered.iter().copied())
+        .unwrap());
+    let for_completion = encoding
+        .render_conversation_for_completion(&convo, Role::Assistant, None)
+        .unwrap();
+    assert!(encoding
+        .ends_at_message_boundary(for_completion.iter().copied())
+        .unwrap());
+
+    // drop the trailing `<|end|>` and a piece of the user message
+    let truncated = &rendered[..rendered.len() - 3];
+    assert!(!encoding
+        .ends_at_message_boundary(truncated.iter().copied())
+        .unwrap());
+}
+
+#[test]
+fn test_render_transcript_indents_tool_interactions() {
+    let encoding = load_harmony_encoding(HarmonyEncodingName::HarmonyGptOss).unwrap();
+    let convo = Conversation::from_messages([
+        Message::from_role_and_content(Role::User, "What is the weather in SF?"),
+        Message::from_role_and_content(Role::Assistant, "Need to use lookup_weather.")
+            .with_channel("analysis"),
+        Message::from_role_and_content(Role::Assistant, "{\"location\": \"San Francisco\"}")
+            .with_channel("commentary")
+            .with_recipient("functions.lookup_weather"),
+        Message::from_author_and_content(
+            Author::new(Role::Tool, "functions.lookup_weather"),
+            "{\"temperature\": 20}",
+        )
+        .with_channel("commentary")
+        .with_recipient("assistant"),
+        Message::from_role_and_content(Role::Assistant, "It is 20 degrees.").with_channel("final"),
+    ]);
+
+    let transcript = encoding.render_transcript(&convo, 4).unwrap();
+    assert_eq!(
+        transcript,
+        [
+            "[user] What is the weather in SF?",
+            "[assistant/analysis] Need to use lookup_weather.",
+            "    [assistant/commentary -> functions.lookup_weather] {\"location\": \"San Francisco\"}",
+            "    [functions.lookup_weather/commentary -> assistant] {\"temperature\": 20}",
+            "[assistant/final] It is 20 degrees.",
+        ]
+        .join("\n")
+    );
+
+    let flat = encoding.render_transcript(&convo, 0).unwrap();
+    assert!(flat.lines().all(|line| line.starts_with('[')));
+}
+
+#[test]
+fn test_render_diff_across_identical_encodings() {
+    let encoding_a = load_harmony_encoding(HarmonyEncodingName::HarmonyGptOss).unwrap();
+    let encoding_b = load_harmony_encoding(HarmonyEncodingName::HarmonyGptOss).unwrap();
+    let convo = Conversation::example();
+    let diff = encoding_a
+        .render_diff(&encoding_b, &convo, Some(Role::Assistant), None)
+        .unwrap();
+    assert_eq!(diff, "");
+    let diff = encoding_a
+        .render_diff(&encoding_b, &convo, None, None)
+        .unwrap();
+    assert_eq!(diff, "");
+}
+
+#[test]
+fn test_line_diff() {
+    let diff = crate::encoding::line_diff("a\nb\nc", "a\nx\nc\nd");
+    assert_eq!(diff, vec!["- b", "+ x", "+ d"]);
+    assert!(crate::encoding::line_diff("same\ntext", "same\ntext").is_empty());
+
+    // long shared context around a small change is diffed line by line
+    let lines = |n: usize, tag: &str| (0..n).map(|i| format!("{tag}{i}\n")).collect::<String>();
+    let a = format!("{}old\n{}", lines(5000, "p"), lines(5000, "s"));
+    let b = format!("{}new\n{}", lines(5000, "p"), lines(5000, "s"));
+    assert_eq!(crate::encoding::line_diff(&a, &b), vec!["- old", "+ new"]);
+
+    // large unrelated inputs are summarised instead of compared
+    let diff = crate::encoding::line_diff(&lines(2000, "a"), &lines(2000, "b"));
+    assert_eq!(diff, vec!["~ 4000 lines differ"]);
+}
+
+#[test]
+fn test_streamable_parser_current_message_partial() {
+    let encoding = load_harmony_encoding(HarmonyEncodingName::HarmonyGptOss).unwrap();
+    let text = "<|start|>assistant<|channel|>commentary to=functions.get_weather<|constrain|>json<|message|>{\"location\": \"Tokyo\"}<|call|>";
+    let tokens = encoding.tokenizer().encode_with_special_tokens(text);
+    let mut parser = StreamableParser::new(encoding, None).unwrap();
+    assert_eq!(parser.current_message_partial().unwrap(), json!(null));
+
+    let mut seen_contents = Vec::new();
+    for token in &tokens[..tokens.len() - 1] {
+        parser.process(*token).unwrap();
+        let partial = parser.current_message_partial().unwrap();
+        if parser.current_channel().is_some() {
+            assert_eq!(partial["role"], "assistant");
+            assert_eq!(partial["channel"], "commentary");
+            assert_eq!(partial["recipient"], "functions.get_weather");
+            assert_eq!(partial["content"], parser.current_content().unwrap());
+            seen_contents.push(parser.current_content().unwrap());
+        }
+    }
+    assert_eq!(seen_contents.first().map(String::as_str), Some(""));
+    assert_eq!(
+        seen_contents.last().map(String::as_str),
+        Some("{\"location\": \"Tokyo\"}")
+    );
+    assert!(seen_contents.windows(2).any(|w| w[0] != w[1]));
+
+    parser.process(*tokens.last().unwrap()).unwrap();
+    assert_eq!(parser.current_message_partial().unwrap(), json!(null));
+}
+
+#[test]
+fn test_render_with_max_messages() {
+    let encoding = load_harmony_encoding(HarmonyEncodingName::HarmonyGptOss).unwrap();
+    let convo = Conversation::example();
+
+    let config = crate::encoding::RenderConversationConfig {
+        max_messages: Some(3),
+        ..Default::default()
+    };
+    let tokens = encoding.render_conversation(&convo, Some(&config)).unwrap();
+    assert_eq!(tokens, encoding.render_conversation(&convo, None).unwrap());
+
+    let config = crate::encoding::RenderConversationConfig {
+        max_messages: Some(2),
+        ..Default::default()
+    };
+    let err = encoding
+        .render_conversation_for_completion(&convo, Role::Assistant, Some(&config))
+        .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "conversation has 3 messages, which exceeds the configured maximum of 2"
+    );
+
+    // a partial JSON config keeps the other defaults and still enforces the limit
+    let config: crate::encoding::RenderConversationConfig =
+        serde_json::from_str(r#"{"max_messages": 2}"#).unwrap();
+    assert!(config.auto_drop_analysis);
+    assert!(encoding.render_conversation(&convo, Some(&config)).is_err());
+}
+
+#[test]
+fn test_special_tokens_diff() {
+    let encoding = load_harmony_encoding(HarmonyEncodingName::HarmonyGptOss).unwrap();
+
+    let same = encoding.special_tokens_diff(&encoding);
+    assert!(same.added.is_empty());
+    assert!(same.removed.is_empty());
+    assert!(same.common.contains(&"<|start|>".to_string()));
+    assert!(same.common.windows(2).all(|w| w[0] < w[1]));
+
+    let mut other = encoding.clone();
+    other.tokenizer = std::sync::Arc::new(
+        CoreBPE::new(
+            [(b"a".to_vec(), 0)],
+            [("<|start|>".to_string(), 1), ("<|custom|>".to_string(), 2)],
+            r"\w+",
+        )
+        .unwrap(),
+    );
+    let diff = encoding.special_tokens_diff(&other);
+    assert_eq!(diff.added, vec!["<|custom|>".to_string()]);
+    assert_eq!(diff.common, vec!["<|start|>".to_string()]);
+    assert!(diff.removed.contains(&"<|end|>".to_string()));
+    assert!(!diff.removed.contains(&"<|start|>".to_string()));
+}
+
+#[test]
+fn test_encode_check_limit() {
+    use std::collections::HashSet;
+
+    let encoding = load_harmony_encoding(HarmonyEncodingName::HarmonyGptOss).unwrap();
+    let text = "The quick brown fox jumps over the lazy dog.";
+    let (expected, _) = encoding.tokenizer().encode(text, &HashSet::new());
+
+    let (tokens, over) = encoding.encode_check_limit(text, &HashSet::new(), expected.len());
+    assert_eq!(tokens, expected);
+    assert!(!over);
+
+    let (tokens, over) = encoding.encode_check_limit(text, &HashSet::new(), expected.len() - 1);
+    assert_eq!(tokens, expected);
+    assert!(over);
+}
+
+#[test]
+fn test_render_skip_empty_messages() {
+    let encoding = load_harmony_encoding(HarmonyEncodingName::HarmonyGptOss).unwrap();
+    let convo = Conversation::from_messages([
+        Message::from_role_and_content(Role::User, "What is 2 + 2?"),
+        Message::from_role_and_content(Role::Assistant, "  \n").with_channel("final"),
+        Message::from_role_and_content(Role::User, "Are you there?"),
+    ]);
+    let non_empty =
+        Conversation::from_messages([convo.messages[0].clone(), convo.messages[2].clone()]);
+
+    let config = crate::encoding::RenderConversationConfig {
+        skip_empty_messages: Some(true),
+        ..Default::default()
+    };
+    let tokens = encoding.render_conversation(&convo, Some(&config)).unwrap();
+    assert_eq!(
+        tokens,
+        encoding.render_conversation(&non_empty, None).unwrap()
+    );
+
+    let default_config = crate::encoding::RenderConversationConfig::default();
+    let tokens = encoding
+        .render_conversation(&convo, Some(&default_config))
+        .unwrap();
+    assert_eq!(tokens, encoding.render_conversation(&convo, None).unwrap());
+    assert_ne!(
+        tokens,
+        encoding.render_conversation(&non_empty, None).unwrap()
+    );
+
+    // a dropped trailing assistant turn must not turn the user's `<|end|>` into `<|return|>`
+    let user = Message::from_role_and_content(Role::User, "hi");
+    let answer = Message::from_role_and_content(Role::Assistant, "hello").with_channel("final");
+    let empty_answer = Message::from_role_and_content(Role::Assistant, "  ").with_channel("final");
+    let tokens = encoding
+        .render_conversation_for_training([&user, &empty_answer], Some(&config))
+        .unwrap();
+    assert_eq!(tokens, encoding.render_conversation([&user], None).unwrap());
+
+    // the last rendered final answer still ends with `<|return|>`
+    let tokens = encoding
+        .render_conversation_for_training([&user, &answer, &empty_answer], Some(&config))
+        .unwrap();
+    assert_eq!(
+        tokens,
+        encoding
+            .render_conversation_for_training([&user, &answer], None)
+            .unwrap()
+    );
+}
+
+#[test]
+fn test_word_token_alignment() {
+    let encoding = load_harmony_encoding(HarmonyEncodingName::HarmonyGptOss).unwrap();
+    let text = "Tokenization splits unbelievably long words, doesn't it?";
+    let alignment = encoding.word_token_alignment(text);
+    let tokens = encoding.tokenizer().encode_ordinary(text);
+
+    assert!(alignment.len() > 1);
+    let mut expected_char = 0;
+    let mut expected_token = 0;
+    for word in &alignment {
+        assert_eq!(word.char_start, expected_char);
+        assert_eq!(word.token_start, expected_token);
+        assert!(word.token_end > word.token_start);
+        assert_eq!(
+            encoding
+                .tokenizer()
+                .decode_utf8(&tokens[word.token_start..word.token_end])
+                .unwrap(),
+            word.word
+        );
+        expected_char += word.word.chars().count();
+        expected_token = word.token_end;
+    }
+    assert_eq!(expected_char, text.chars().count());
+    assert_eq!(expected_token, tokens.len());
+
+    // a pre-tokenizer that skips characters must not shift later offsets
+    let mut sparse = encoding.clone();
+    sparse.tokenizer = std::sync::Arc::new(
+        CoreBPE::new(
+            (0..=255u8).map(|b| (vec![b], b as Rank)),
+            [("<|start|>".to_string(), 256)],
+            r"\w+",
+        )
+        .unwrap(),
+    );
+    let alignment = sparse.word_token_alignment("héé,  ok");
+    let starts: Vec<_> = alignment
+        .iter()
+        .map(|w| (w.word.as_str(), w.char_start, w.token_start, w.token_end))
+        .collect();
+    assert_eq!(starts, vec![("héé", 0, 0, 5), ("ok", 6, 5, 7)]);
+}
@@ -287,6 +287,23 @@ impl CoreBPE {
         ret
     }
 
+    /// Like [`Self::encode_ordinary`], but keeps the tokens of each pre-tokenizer
+    /// piece together with the piece's byte range in `text`.
+    pub fn encode_ordinary_by_piece(&self, text: &str) -> Vec<(std::ops::Range<usize>, Vec<Rank>)> {
+        let regex = self._get_tl_regex();
+        let mut ret = vec![];
+        for mat in regex.find_iter(text) {
+            let mat = mat.unwrap();
+            let piece = mat.as_str().as_bytes();
+            let tokens = match self.encoder.get(piece) {
+                Some(token) => vec![*token],
+                None => byte_pair_encode(piece, &self.encoder),
+            };
+            ret.push((mat.start()..mat.end(), tokens));
+        }
+        ret
+    }
+
     pub fn encode(&self, text: &str, allowed_special: &HashSet<&str>) -> (Vec<Rank>, usize) {
         let special_regex = self._get_tl_special_regex();
         let regex = self._get_tl_regex();
@@ -49,6 +49,10 @@ export interface Conversation {
 
 export interface RenderConversationConfig {
   auto_drop_analysis?: boolean;
+  system_author?: string;
+  channel_overrides?: Record<number, string>;
+  max_messages?: number;
+  skip_empty_messages?: boolean;
 }
 
 export interface ToolNamespaceConfig {
@@ -58,6 +62,45 @@ export interface ToolNamespaceConfig {
 }
 "#;
 
+/// Convert an optional JS render config into a `RenderConversationConfig`.
+/// Missing keys keep their defaults.
+fn parse_render_config(
+    config: JsRenderConversationConfig,
+) -> Result<Option<crate::encoding::RenderConversationConfig>, JsValue> {
+    #[derive(Deserialize)]
+    struct Config {
+        auto_drop_analysis: Option<bool>,
+        system_author: Option<String>,
+        // JS object keys are strings, so indices are parsed below
+        channel_overrides: Option<std::collections::HashMap<String, String>>,
+        max_messages: Option<usize>,
+        skip_empty_messages: Option<bool>,
+    }
+    let config: JsValue = config.into();
+    if config.is_undefined() || config.is_null() {
+        return Ok(None);
+    }
+    let cfg: Config = serde_wasm_bindgen::from_value(config)
+        .map_err(|e| JsValue::from_str(&format!("invalid config: {e}")))?;
+    let channel_overrides = cfg
+        .channel_overrides
+        .unwrap_or_default()
+        .into_iter()
+        .map(|(idx, channel)| {
+            idx.parse::<usize>()
+                .map(|idx| (idx, channel))
+                .map_err(|_| JsValue::from_str(&format!("invalid channel override index: {idx}")))
+        })
+        .collect::<Result<_, _>>()?;
+    Ok(Some(crate::encoding::RenderConversationConfig {
+        auto_drop_analysis: cfg.auto_drop_analysis.unwrap_or(true),
+        system_author: cfg.system_author,
+        channel_overrides,
+        max_messages: cfg.max_messages,
+        skip_empty_messages: cfg.skip_empty_messages,
+    }))
+}
+
 #[wasm_bindgen]
 pub struct JsHarmonyEncoding {
     inner: HarmonyEncoding,
@@ -82,20 +125,7 @@ impl JsHarmonyEncoding {
             .map_err(|e| JsValue::from_str(&format!("invalid conversation JSON: {e}")))?;
         let role = Role::try_from(next_turn_role)
             .map_err(|_| JsValue::from_str(&format!("unknown role: {next_turn_role}")))?;
-        #[derive(Deserialize)]
-        struct Config {
-            auto_drop_analysis: Option<bool>,
-        }
-        let config: JsValue = config.into();
-        let rust_config = if config.is_undefined() || config.is_null() {
-            None
-        } else {
-            let cfg: Config = serde_wasm_bindgen::from_value(config)
-                .map_err(|e| JsValue::from_str(&format!("invalid config: {e}")))?;
-            Some(crate::encoding::RenderConversationConfig {
-                auto_drop_analysis: cfg.auto_drop_analysis.unwrap_or(true),
-            })
-        };
+        let rust_config = parse_render_config(config)?;
         self.inner
             .render_conversation_for_completion(&conversation, role, rust_config.as_ref())
             .map_err(|e| JsValue::from_str(&e.to_string()))
@@ -110,20 +140,7 @@ impl JsHarmonyEncoding {
         let conversation: JsValue = conversation.into();
         let conversation: crate::chat::Conversation = serde_wasm_bindgen::from_value(conversation)
             .map_err(|e| JsValue::from_str(&format!("invalid conversation JSON: {e}")))?;
-        #[derive(Deserialize)]
-        struct Config {
-            auto_drop_analysis: Option<bool>,
-        }
-        let config: JsValue = config.into();
-        let rust_config = if config.is_undefined() || config.is_null() {
-            None
-        } else {
-            let cfg: Config = serde_wasm_bindgen::from_value(config)
-                .map_err(|e| JsValue::from_str(&format!("invalid config: {e}")))?;
-            Some(crate::encoding::RenderConversationConfig {
-                auto_drop_analysis: cfg.auto_drop_analysis.unwrap_or(true),
-            })
-        };
+        let rust_config = parse_render_config(config)?;
         self.inner
             .render_conversation(&conversation, rust_config.as_ref())
             .map_err(|e| JsValue::from_str(&e.to_string()))
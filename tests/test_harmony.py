@@ -981,3 +981,71 @@ def test_streamable_parser_tool_call_with_constrain_adjacent():
     ]
 
     assert parser.messages == expected
+
+
+def test_render_config_system_author():
+    encoding = load_harmony_encoding(HarmonyEncodingName.HARMONY_GPT_OSS)
+    convo = Conversation.from_messages(
+        [
+            Message.from_role_and_content(Role.SYSTEM, SystemContent.new()),
+            Message.from_role_and_content(Role.USER, "What is 2 + 2?"),
+        ]
+    )
+
+    tokens = encoding.render_conversation_for_completion(
+        convo,
+        Role.ASSISTANT,
+        RenderConversationConfig(system_author="orchestrator"),
+    )
+    assert encoding.decode_utf8(tokens).startswith(
+        "<|start|>system:orchestrator<|message|>"
+    )
+
+
+def test_render_config_channel_overrides():
+    encoding = load_harmony_encoding(HarmonyEncodingName.HARMONY_GPT_OSS)
+    convo = Conversation.from_messages(
+        [
+            Message.from_role_and_content(Role.USER, "What is 2 + 2?"),
+            Message.from_role_and_content(
+                Role.ASSISTANT, "2 + 2 equals 4."
+            ).with_channel("final"),
+        ]
+    )
+
+    tokens = encoding.render_conversation(
+        convo, RenderConversationConfig(channel_overrides={1: "commentary"})
+    )
+    decoded = encoding.decode_utf8(tokens)
+    assert "<|channel|>commentary<|message|>2 + 2 equals 4." in decoded
+
+    with pytest.raises(RuntimeError, match="out of range"):
+        encoding.render_conversation(
+            convo, RenderConversationConfig(channel_overrides={5: "commentary"})
+        )
+
+
+def test_render_config_max_messages():
+    encoding = load_harmony_encoding(HarmonyEncodingName.HARMONY_GPT_OSS)
+    convo = Conversation.from_messages(
+        [
+            Message.from_role_and_content(Role.USER, "What is 2 + 2?"),
+            Message.from_role_and_content(Role.USER, "Are you there?"),
+        ]
+    )
+
+    encoding.render_conversation(convo, RenderConversationConfig(max_messages=2))
+    with pytest.raises(RuntimeError, match="exceeds the configured maximum of 1"):
+        encoding.render_conversation(convo, RenderConversationConfig(max_messages=1))
+
+
+def test_render_config_skip_empty_messages():
+    encoding = load_harmony_encoding(HarmonyEncodingName.HARMONY_GPT_OSS)
+    user = Message.from_role_and_content(Role.USER, "What is 2 + 2?")
+    empty = Message.from_role_and_content(Role.ASSISTANT, "  ").with_channel("final")
+
+    tokens = encoding.render_conversation(
+        Conversation.from_messages([user, empty]),
+        RenderConversationConfig(skip_empty_messages=True),
+    )
+    assert tokens == encoding.render_conversation(Conversation.from_messages([user]))